@@ -1,49 +1,379 @@
 use core::panic;
+use pyo3_build_config::{InterpreterConfig, PythonVersion};
 use quote::{format_ident, quote, ToTokens};
+use serde_json::{Map, Value};
+use similar::TextDiff;
 use std::error::Error;
-use std::fs::File;
+use std::fs::{self, File};
 use std::io::{Read, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 use syn::{
     parse_file, AngleBracketedGenericArguments, GenericArgument, Ident, Item, ItemMod, PatIdent,
     PathArguments, PathSegment, ReturnType, Type,
 };
 
+/// When set (to anything but `0` or an empty string), generated files are written to a
+/// staging directory under `OUT_DIR` and a diff against the files in the tree is printed
+/// instead, leaving the tree untouched.
+const DRY_RUN_ENV: &str = "TAURI_PY_CODEGEN_DRY_RUN";
+
+/// Stands in for `..` in staged paths.
+const STAGED_PARENT_DIR: &str = "__parent__";
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     std::env::set_var("PYTHONPATH", "./");
     std::env::set_var("PYTHONDONTWRITEBYTECODE", "1");
+    // Printing any `rerun-if` line turns off Cargo's default of rerunning on every package
+    // change, so list all inputs of the code generation explicitly
+    println!("cargo:rerun-if-env-changed={}", DRY_RUN_ENV);
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=state.proto");
+    println!("cargo:rerun-if-changed=events.proto");
+    rerun_if_python_changed(Path::new("python/src"))?;
+
+    let linked_python = pyo3_build_config::get();
+    record_linked_python(linked_python);
+
+    if linked_python.abi3 {
+        check_abi3_dependencies(&linked_python.version)
+            .expect("Failed to check Python dependencies for abi3 support");
+    }
+
+    let out = Output::from_env();
 
     pyo3_bindgen::Codegen::default()
         .module_name("python.src")
         .unwrap()
-        .build("src/gen/py_bindings.rs")
+        .build(out.path("src/gen/py_bindings.rs"))
         .unwrap();
 
+    let retry_policies = read_retry_policies("python.src").expect("Failed to read retry policies");
+
     generate_commands_from_py_bindings(
-        "src/gen/py_bindings.rs",
-        "src/gen/py_commands.rs",
+        out.path("src/gen/py_bindings.rs"),
+        out.path("src/gen/py_commands.rs"),
         vec!["python", "src"],
+        &retry_policies,
     )
     .expect("Failed to generate Tauri commands");
 
     protobuf_codegen::Codegen::new()
-        .out_dir("src/gen/state")
+        .out_dir(out.path("src/gen/state"))
         .inputs(&["state.proto"])
         .includes(&["."])
         .run()
         .expect("Failed to generate protobuf code");
 
-    gen_python_from_proto("state.proto", "python/src/gen", ".");
+    gen_python_from_proto("state.proto", &out.path("python/src/gen"), ".");
+
+    gen_python_from_proto("events.proto", &out.path("python/src/gen"), ".");
+    gen_ts_from_proto("events.proto", &out.path("../src/gen"), ".")
+        .expect("Failed to generate TypeScript event schemas");
+
+    format(&out.path("src/gen/py_bindings.rs"));
+    format(&out.path("src/gen/py_commands.rs"));
 
-    format("src/gen/py_bindings.rs");
-    format("src/gen/py_commands.rs");
+    out.report().expect("Failed to diff generated code");
 
     tauri_build::build();
 
     Ok(())
 }
 
+/// Decides where generated files go: straight into the tree, or into a staging directory
+/// that mirrors the tree layout when running in dry-run mode.
+struct Output {
+    staging: Option<PathBuf>,
+}
+
+impl Output {
+    fn from_env() -> Self {
+        let dry_run = std::env::var(DRY_RUN_ENV)
+            .map(|v| !v.is_empty() && v != "0")
+            .unwrap_or(false);
+
+        if !dry_run {
+            return Output { staging: None };
+        }
+
+        let staging = PathBuf::from(std::env::var("OUT_DIR").unwrap()).join("codegen-preview");
+        if staging.exists() {
+            fs::remove_dir_all(&staging).expect("Failed to clear codegen staging directory");
+        }
+
+        Output {
+            staging: Some(staging),
+        }
+    }
+
+    /// Returns the path a generated file (relative to the crate root) should be written to,
+    /// creating the parent directories in the staging area if needed.
+    fn path(&self, path: &str) -> String {
+        match &self.staging {
+            None => path.to_string(),
+            Some(staging) => {
+                // Keep files outside the crate (like the frontend's) inside the staging area
+                let staged = staging.join(path.replace("..", STAGED_PARENT_DIR));
+                let dir = if Path::new(path).extension().is_some() {
+                    staged.parent().unwrap().to_path_buf()
+                } else {
+                    staged.clone()
+                };
+                fs::create_dir_all(dir).expect("Failed to create codegen staging directory");
+                staged.to_string_lossy().into_owned()
+            }
+        }
+    }
+
+    /// In dry-run mode, prints a unified diff of every staged file against its counterpart in
+    /// the tree as cargo warnings and saves it to `OUT_DIR/codegen.diff`.
+    fn report(&self) -> Result<(), Box<dyn Error>> {
+        let Some(staging) = &self.staging else {
+            return Ok(());
+        };
+
+        let mut staged_files = Vec::new();
+        collect_files(staging, &mut staged_files)?;
+        staged_files.sort();
+
+        let mut diff = String::new();
+        for staged in staged_files {
+            let relative = PathBuf::from(
+                staged
+                    .strip_prefix(staging)?
+                    .to_string_lossy()
+                    .replace(STAGED_PARENT_DIR, ".."),
+            );
+            let new = fs::read_to_string(&staged)?;
+            let old = fs::read_to_string(&relative).unwrap_or_default();
+            if old == new {
+                continue;
+            }
+
+            let name = relative.to_string_lossy();
+            diff.push_str(
+                &TextDiff::from_lines(&old, &new)
+                    .unified_diff()
+                    .header(&format!("a/{}", name), &format!("b/{}", name))
+                    .to_string(),
+            );
+        }
+
+        let diff_path = staging.with_file_name("codegen.diff");
+        fs::write(&diff_path, &diff)?;
+
+        if diff.is_empty() {
+            println!("cargo:warning=codegen dry-run: generated code is up to date");
+        } else {
+            println!(
+                "cargo:warning=codegen dry-run: generated code would change (saved to {})",
+                diff_path.display()
+            );
+            for line in diff.lines() {
+                println!("cargo:warning={}", line);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Reruns the build when a Python source changes. Watches the files rather than the
+/// directory, since `python/src/gen` is written by this very build and would make it rerun
+/// every time.
+fn rerun_if_python_changed(dir: &Path) -> std::io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            if path
+                .file_name()
+                .is_some_and(|name| name != "gen" && name != "__pycache__")
+            {
+                rerun_if_python_changed(&path)?;
+            }
+        } else if path.extension().is_some_and(|ext| ext == "py") {
+            println!("cargo:rerun-if-changed={}", path.display());
+        }
+    }
+    Ok(())
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_files(&path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// The interpreter of the project environment: `PYO3_PYTHON`, the uv virtualenv in
+/// `python/.venv`, or whatever `python3` is on `PATH`.
+fn python_interpreter() -> String {
+    println!("cargo:rerun-if-env-changed=PYO3_PYTHON");
+
+    std::env::var("PYO3_PYTHON").unwrap_or_else(|_| {
+        ["python/.venv/bin/python", "python/.venv/Scripts/python.exe"]
+            .into_iter()
+            .find(|path| Path::new(path).exists())
+            .unwrap_or("python3")
+            .to_string()
+    })
+}
+
+/// Collects the retry policies attached to functions of `module` with the
+/// `tauri_py.retry` decorator, keyed by function name.
+fn read_retry_policies(module: &str) -> Result<Map<String, Value>, Box<dyn Error>> {
+    let script = format!(
+        r#"
+import importlib, inspect, json
+module = importlib.import_module("{}")
+print(json.dumps({{
+    name: func.__tauri_retry__
+    for name, func in vars(module).items()
+    if inspect.isfunction(func) and hasattr(func, "__tauri_retry__")
+}}))
+"#,
+        module
+    );
+
+    let python = python_interpreter();
+    let output = Command::new(&python).arg("-c").arg(script).output()?;
+    if !output.status.success() {
+        return Err(format!(
+            "Failed to read retry policies with {}: {}",
+            python,
+            String::from_utf8_lossy(&output.stderr)
+        )
+        .into());
+    }
+
+    Ok(serde_json::from_slice(&output.stdout)?)
+}
+
+/// Tells the app which Python it is linked against, so it can refuse interpreters it can't
+/// run with. Only Windows builds with the `abi3` feature link the version-agnostic
+/// `python3.dll`; everywhere else the versioned `libpython3.X` is linked even for `abi3`.
+fn record_linked_python(config: &InterpreterConfig) {
+    let target_os = std::env::var("CARGO_CFG_TARGET_OS").unwrap_or_default();
+    let stable_abi = config.abi3 && target_os == "windows";
+
+    let mut linked_version = (config.version.major, config.version.minor);
+    if config.abi3 && !stable_abi {
+        // Here `version` is the minimum stable ABI version, not the one of the linked
+        // library, which `lib_name` (e.g. `python3.13`) still names
+        if let Some(version) = config
+            .lib_name
+            .as_deref()
+            .and_then(|name| name.strip_prefix("python"))
+            .and_then(|version| version.split_once('.'))
+            .and_then(|(major, minor)| Some((major.parse().ok()?, minor.parse().ok()?)))
+        {
+            linked_version = version;
+        }
+    }
+
+    println!(
+        "cargo:rustc-env=TAURI_PY_PYTHON_VERSION={}.{}",
+        linked_version.0, linked_version.1
+    );
+    println!("cargo:rustc-env=TAURI_PY_PYTHON_STABLE_ABI={}", stable_abi);
+}
+
+/// With the `abi3` feature the app only depends on Python's stable ABI, so every compiled
+/// package bundled with it has to be built for the stable ABI (or be pure Python) as well.
+/// Checks the wheel tags of everything installed in the project environment, rejecting
+/// `abi3` wheels that need a newer Python than `minimum`.
+fn check_abi3_dependencies(minimum: &PythonVersion) -> Result<(), Box<dyn Error>> {
+    let python = python_interpreter();
+    let output = Command::new(&python)
+        .arg("-c")
+        .arg(
+            "import sysconfig; p = sysconfig.get_paths(); print(p['purelib']); print(p['platlib'])",
+        )
+        .output()?;
+    if !output.status.success() {
+        return Err(format!(
+            "Failed to locate site-packages of {}: {}",
+            python,
+            String::from_utf8_lossy(&output.stderr)
+        )
+        .into());
+    }
+
+    let mut site_dirs: Vec<String> = String::from_utf8(output.stdout)?
+        .lines()
+        .map(str::to_string)
+        .collect();
+    site_dirs.dedup();
+
+    let mut incompatible = Vec::new();
+    for site_dir in site_dirs {
+        let Ok(entries) = fs::read_dir(&site_dir) else {
+            continue;
+        };
+        for entry in entries {
+            let dist_info = entry?.path();
+            if dist_info.extension() != Some("dist-info".as_ref()) {
+                continue;
+            }
+            let Ok(wheel) = fs::read_to_string(dist_info.join("WHEEL")) else {
+                continue;
+            };
+
+            // Tags look like `cp38-abi3-manylinux_2_17_x86_64` or `py3-none-any`
+            let tags: Vec<&str> = wheel
+                .lines()
+                .filter_map(|line| line.strip_prefix("Tag:"))
+                .map(str::trim)
+                .collect();
+            let compatible = tags.iter().any(|tag| {
+                let mut parts = tag.split('-');
+                let (python, abi) = (parts.next().unwrap_or_default(), parts.next());
+                match abi {
+                    Some("none") => true,
+                    // `cp38` is the oldest Python a stable ABI wheel runs on; compressed tags
+                    // like `cp38.cp39` list several
+                    Some("abi3") => python.split('.').any(|python| {
+                        python_tag_version(python)
+                            .is_some_and(|version| version <= (minimum.major, minimum.minor))
+                    }),
+                    _ => false,
+                }
+            });
+            if !compatible {
+                let name = dist_info
+                    .file_stem()
+                    .unwrap()
+                    .to_string_lossy()
+                    .into_owned();
+                incompatible.push(format!("{} ({})", name, tags.join(", ")));
+            }
+        }
+    }
+
+    if !incompatible.is_empty() {
+        return Err(format!(
+            "The abi3 feature is enabled but these Python dependencies are not built for the stable ABI: {}",
+            incompatible.join("; ")
+        )
+        .into());
+    }
+
+    Ok(())
+}
+
+/// Parses a CPython tag like `cp312` into `(3, 12)`.
+fn python_tag_version(tag: &str) -> Option<(u8, u8)> {
+    let digits = tag.strip_prefix("cp")?;
+    let (major, minor) = digits.split_at_checked(1)?;
+    Some((major.parse().ok()?, minor.parse().ok()?))
+}
+
 fn gen_python_from_proto(file: &str, out_dir: &str, proto_path: &str) {
     let output = Command::new("protoc")
         .arg(format!("--proto_path={}", proto_path))
@@ -61,6 +391,44 @@ fn gen_python_from_proto(file: &str, out_dir: &str, proto_path: &str) {
     }
 }
 
+/// Generates TypeScript schemas for the messages in a proto file with `protoc-gen-es`
+/// (from the frontend's dev dependencies), so the frontend can decode the binary event
+/// payloads emitted from Python with `@bufbuild/protobuf`.
+fn gen_ts_from_proto(file: &str, out_dir: &str, proto_path: &str) -> Result<(), Box<dyn Error>> {
+    let plugin = if cfg!(windows) {
+        "../node_modules/.bin/protoc-gen-es.cmd"
+    } else {
+        "../node_modules/.bin/protoc-gen-es"
+    };
+    if !Path::new(plugin).exists() {
+        return Err(format!(
+            "{} not found, install the frontend dependencies with `bun install` first",
+            plugin
+        )
+        .into());
+    }
+
+    // `src/gen` is ignored by git, and protoc doesn't create missing output directories
+    fs::create_dir_all(out_dir)?;
+    let output = Command::new("protoc")
+        .arg(format!("--proto_path={}", proto_path))
+        .arg(format!("--plugin=protoc-gen-es={}", plugin))
+        .arg(format!("--es_out={}", out_dir))
+        .arg("--es_opt=target=ts")
+        .arg(file)
+        .output()?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "protoc-gen-es failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        )
+        .into());
+    }
+
+    Ok(())
+}
+
 fn format(path: &str) {
     let output = Command::new("rustfmt")
         .arg(path)
@@ -78,6 +446,7 @@ fn generate_commands_from_py_bindings<P: AsRef<Path>>(
     input_path: P,
     output_path: P,
     modules: Vec<&str>,
+    retry_policies: &Map<String, Value>,
 ) -> Result<(), Box<dyn Error>> {
     // Read the input Rust file into a string
     let mut input_file = File::open(input_path)?;
@@ -94,6 +463,8 @@ fn generate_commands_from_py_bindings<P: AsRef<Path>>(
 
     output_code.push_str(
         &quote! {
+         use crate::dispatch::{CommandError, Dispatcher};
+         use crate::retry::RetryPolicy;
          use crate::gen::py_bindings::#(#module_idents)::* as bindings;
         }
         .to_string(),
@@ -110,6 +481,9 @@ fn generate_commands_from_py_bindings<P: AsRef<Path>>(
         module = get_tail_mod(&module, module_name);
     }
 
+    let mut policies = Vec::new();
+    let mut command_names = Vec::new();
+
     // Process items in the module
     if let Some((_, items)) = module.clone().content {
         for item in items {
@@ -131,32 +505,78 @@ fn generate_commands_from_py_bindings<P: AsRef<Path>>(
                     ReturnType::Default => panic!("Function must have a return type"),
                 };
 
+                // A `ctx` parameter opts into the call context, which is passed in by the
+                // dispatcher instead of coming from the frontend
+                let command_args: Vec<_> = remaining_args
+                    .iter()
+                    .filter(|arg| !is_context_arg(arg))
+                    .collect();
+
                 // Convert function arguments to appropriate quote format
                 let args_list = remaining_args.iter().map(|arg| {
-                    let arg_name = match arg {
-                        syn::FnArg::Typed(pat_type) => &pat_type.pat,
+                    let pat_type = match arg {
+                        syn::FnArg::Typed(pat_type) => pat_type,
                         _ => panic!("Unexpected argument type"),
                     };
-                    quote! { #arg_name }
+                    if !is_context_arg(arg) {
+                        let arg_name = &pat_type.pat;
+                        quote! { #arg_name }
+                    } else if let Type::Reference(_) = *pat_type.ty {
+                        quote! { &__tauri_py_ctx.to_py(__tauri_py_gil)? }
+                    } else {
+                        quote! { __tauri_py_ctx.to_py(__tauri_py_gil)? }
+                    }
                 });
 
-                // Build the transformed function
+                // Build the transformed function; hooks, error mapping and the GIL are
+                // handled by the dispatcher. Commands are async so that Python calls and
+                // retry backoffs run on a worker thread instead of blocking the main thread.
+                // Tauri resolves the state and window by type, so their names only need to
+                // stay clear of the Python parameters
+                let command_name = func_name.to_string();
                 let transformed_fn = quote! {
-                    #[tauri::command]
-                    pub fn #func_name(#(#remaining_args),*) -> Result<#ret_type, String> {
-                        pyo3::Python::with_gil(|py| {
-                            bindings::#func_name(py, #(#args_list),*).map_err(|e| e.to_string())
-                        })
+                    #[tauri::command(async)]
+                    pub fn #func_name(
+                        __tauri_py_dispatcher: tauri::State<'_, Dispatcher>,
+                        __tauri_py_window: tauri::Window,
+                        #(#command_args),*
+                    ) -> Result<#ret_type, CommandError> {
+                        __tauri_py_dispatcher.call(
+                            #command_name,
+                            __tauri_py_window.label(),
+                            |__tauri_py_gil, __tauri_py_ctx| {
+                                bindings::#func_name(__tauri_py_gil, #(#args_list),*)
+                            },
+                        )
                     }
                 };
 
                 // Append the transformed function to the output code
                 output_code.push_str(&transformed_fn.to_string());
                 output_code.push_str("\n\n");
+                command_names.push(command_name.clone());
+
+                if let Some(policy) = retry_policies.get(&command_name) {
+                    policies.push(generate_retry_policy(&command_name, policy)?);
+                }
             }
         }
     }
 
+    let commands_const = quote! {
+        /// Names of all generated commands, whether registered with the invoke handler or not.
+        pub const COMMANDS: &[&str] = &[#(#command_names),*];
+    };
+    output_code.push_str(&commands_const.to_string());
+    output_code.push_str("\n\n");
+
+    let retry_policies_fn = quote! {
+        pub fn retry_policies() -> Vec<(&'static str, RetryPolicy)> {
+            vec![#(#policies),*]
+        }
+    };
+    output_code.push_str(&retry_policies_fn.to_string());
+
     // Write the transformed code to the output file
     let mut output_file = File::create(output_path)?;
     output_file.write_all(output_code.as_bytes())?;
@@ -164,6 +584,45 @@ fn generate_commands_from_py_bindings<P: AsRef<Path>>(
     Ok(())
 }
 
+/// Turns a policy recorded by `tauri_py.retry` into a `(command, RetryPolicy)` tuple.
+fn generate_retry_policy(
+    command_name: &str,
+    policy: &Value,
+) -> Result<proc_macro2::TokenStream, Box<dyn Error>> {
+    let invalid = || format!("Invalid retry policy for {}: {}", command_name, policy);
+
+    let max_attempts = policy["max_attempts"].as_u64().ok_or_else(invalid)? as u32;
+    let initial_backoff_ms = policy["backoff_ms"].as_u64().ok_or_else(invalid)?;
+    let max_backoff_ms = policy["max_backoff_ms"].as_u64().ok_or_else(invalid)?;
+    let multiplier = policy["multiplier"]
+        .as_f64()
+        .filter(|multiplier| *multiplier >= 0.0)
+        .ok_or_else(invalid)?;
+    let retry_on = policy["on"]
+        .as_array()
+        .ok_or_else(invalid)?
+        .iter()
+        .map(|name| name.as_str().map(str::to_string).ok_or_else(invalid))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(quote! {
+        (#command_name, RetryPolicy {
+            max_attempts: #max_attempts,
+            initial_backoff: std::time::Duration::from_millis(#initial_backoff_ms),
+            multiplier: #multiplier,
+            max_backoff: std::time::Duration::from_millis(#max_backoff_ms),
+            retry_on: vec![#(#retry_on.to_string()),*],
+        })
+    })
+}
+
+fn is_context_arg(arg: &syn::FnArg) -> bool {
+    match arg {
+        syn::FnArg::Typed(pat_type) => pat_type.pat.to_token_stream().to_string() == "ctx",
+        _ => false,
+    }
+}
+
 fn get_tail_mod(module: &ItemMod, module_name: &&str) -> ItemMod {
     module
         .content
@@ -0,0 +1,90 @@
+"""Helpers for Python functions exposed as Tauri commands."""
+
+import time
+from dataclasses import dataclass, field
+from typing import Any, Callable, Dict, Iterable, Optional, TypeVar, Union
+
+F = TypeVar("F", bound=Callable)
+
+
+@dataclass(frozen=True)
+class Context:
+    """The call context, passed to command functions that declare a `ctx` parameter.
+
+    `invocation_id` matches the id in Rust logs, emitted events and the error sent to the
+    frontend, so include it in your own logs for end-to-end correlation.
+    """
+
+    command: str
+    invocation_id: str
+    window: str
+    session_id: Optional[str] = None
+    user_id: Optional[str] = None
+    locale: Optional[str] = None
+    # Unix timestamp after which the caller no longer waits for the result
+    deadline: Optional[float] = None
+    metadata: Dict[str, Any] = field(default_factory=dict)
+
+    def remaining(self) -> Optional[float]:
+        """Seconds left until the deadline, if there is one."""
+        if self.deadline is None:
+            return None
+        return max(0.0, self.deadline - time.time())
+
+
+def retry(
+    max_attempts: int = 3,
+    backoff: float = 0.1,
+    max_backoff: float = 5.0,
+    multiplier: float = 2.0,
+    on: Iterable[Union[type, str]] = (),
+) -> Callable[[F], F]:
+    """Retries the command when it raises, waiting `backoff` seconds before the first retry
+    and `multiplier` times longer before each next one, up to `max_backoff`.
+
+    `on` limits retries to the given exception classes (or class names) and their
+    subclasses; by default any exception is retried. Only use it for idempotent functions.
+
+    The policy is read at build time and enforced by the generated Rust command; the
+    function itself is returned unchanged.
+    """
+
+    if max_attempts < 1:
+        raise ValueError("max_attempts must be at least 1")
+    if min(backoff, max_backoff, multiplier) < 0:
+        raise ValueError("backoff, max_backoff and multiplier must not be negative")
+
+    def decorate(func: F) -> F:
+        func.__tauri_retry__ = {
+            "max_attempts": max_attempts,
+            "backoff_ms": round(backoff * 1000),
+            "max_backoff_ms": round(max_backoff * 1000),
+            "multiplier": float(multiplier),
+            "on": [cls if isinstance(cls, str) else cls.__qualname__ for cls in on],
+        }
+        return func
+
+    return decorate
+
+
+def emit(
+    event: str,
+    message: Any,
+    window: Optional[str] = None,
+    ctx: Optional[Context] = None,
+) -> None:
+    """Emits a protobuf message (from a class generated from `events.proto`) as a binary
+    event, to `window` or to all windows. The frontend decodes it with `decodeEvent` from
+    `src/events.ts`.
+
+    Pass the call's `ctx` to tag the event with its invocation id.
+    """
+    import tauri  # Provided by the app at runtime
+
+    tauri.emit_message(
+        event,
+        type(message).DESCRIPTOR.full_name,
+        message.SerializeToString(),
+        window,
+        ctx.invocation_id if ctx else None,
+    )
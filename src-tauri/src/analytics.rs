@@ -0,0 +1,198 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::State;
+
+use crate::dispatch::{CallContext, CommandError, CommandHooks};
+
+const ANALYTICS_FILE: &str = "analytics.json";
+
+/// Receives usage reports when the frontend asks to submit them, e.g. to post them to a
+/// product analytics endpoint.
+pub trait ReportSink: Send + Sync {
+    fn submit(&self, report: &UsageReport) -> Result<(), String>;
+}
+
+/// Usage counters per command. Only command names, call counts, error counts and durations
+/// are recorded; never arguments, results or error messages.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UsageReport {
+    /// Unix timestamp (seconds) of when recording started.
+    pub since: u64,
+    pub commands: BTreeMap<String, CommandUsage>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CommandUsage {
+    pub calls: u64,
+    pub errors: u64,
+    pub error_rate: f64,
+    pub total_duration_ms: u64,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct Stored {
+    enabled: bool,
+    report: UsageReport,
+}
+
+/// Opt-in local usage analytics. Registered as dispatcher hooks to see every command call,
+/// and records nothing until enabled with `set_analytics_enabled`.
+#[derive(Default)]
+pub struct Analytics {
+    path: Option<PathBuf>,
+    stored: Mutex<Stored>,
+    sinks: Vec<Box<dyn ReportSink>>,
+}
+
+impl Analytics {
+    /// Loads previously recorded usage from `dir`, which is also where [`Analytics::save`]
+    /// writes it back.
+    pub fn load(dir: Option<PathBuf>) -> Self {
+        let path = dir.map(|dir| dir.join(ANALYTICS_FILE));
+        let stored = path
+            .as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default();
+
+        Analytics {
+            path,
+            stored: Mutex::new(stored),
+            sinks: Vec::new(),
+        }
+    }
+
+    pub fn sink(mut self, sink: impl ReportSink + 'static) -> Self {
+        self.sinks.push(Box::new(sink));
+        self
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let stored = self.stored.lock().unwrap();
+        fs::write(path, serde_json::to_vec_pretty(&*stored)?)
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.stored.lock().unwrap().enabled
+    }
+
+    pub fn report(&self) -> UsageReport {
+        self.stored.lock().unwrap().report.clone()
+    }
+
+    fn set_enabled(&self, enabled: bool) {
+        let mut stored = self.stored.lock().unwrap();
+        if stored.enabled != enabled {
+            stored.enabled = enabled;
+            stored.report = UsageReport {
+                since: if enabled { now() } else { 0 },
+                commands: BTreeMap::new(),
+            };
+        }
+    }
+
+    fn clear(&self) {
+        let mut stored = self.stored.lock().unwrap();
+        stored.report.commands.clear();
+        stored.report.since = now();
+    }
+}
+
+impl CommandHooks for Analytics {
+    fn post_call(&self, ctx: &CallContext, result: Result<(), &CommandError>, elapsed: Duration) {
+        let mut stored = self.stored.lock().unwrap();
+        if !stored.enabled {
+            return;
+        }
+
+        let usage = stored
+            .report
+            .commands
+            .entry(ctx.command.clone())
+            .or_default();
+        usage.calls += 1;
+        if result.is_err() {
+            usage.errors += 1;
+        }
+        usage.error_rate = usage.errors as f64 / usage.calls as f64;
+        usage.total_duration_ms += elapsed.as_millis() as u64;
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+fn save(analytics: &Analytics) -> Result<(), CommandError> {
+    analytics
+        .save()
+        .map_err(|e| CommandError::new("analytics_error", e.to_string()))
+}
+
+#[tauri::command]
+pub fn get_analytics_enabled(analytics: State<'_, Arc<Analytics>>) -> bool {
+    analytics.enabled()
+}
+
+/// Turning analytics on or off starts over with an empty report.
+#[tauri::command]
+pub fn set_analytics_enabled(
+    analytics: State<'_, Arc<Analytics>>,
+    enabled: bool,
+) -> Result<(), CommandError> {
+    analytics.set_enabled(enabled);
+    save(&analytics)
+}
+
+/// Returns the recorded usage, for display or export from the frontend.
+#[tauri::command]
+pub fn get_usage_report(analytics: State<'_, Arc<Analytics>>) -> UsageReport {
+    analytics.report()
+}
+
+#[tauri::command]
+pub fn clear_usage_report(analytics: State<'_, Arc<Analytics>>) -> Result<(), CommandError> {
+    analytics.clear();
+    save(&analytics)
+}
+
+/// Hands the report to every registered [`ReportSink`] and starts a new report once they
+/// all accepted it. Runs off the main thread, as sinks may take a while, e.g. to post it.
+#[tauri::command(async)]
+pub fn submit_usage_report(analytics: State<'_, Arc<Analytics>>) -> Result<(), CommandError> {
+    if !analytics.enabled() {
+        return Err(CommandError::new(
+            "analytics_disabled",
+            "Usage analytics are not enabled",
+        ));
+    }
+    // Without a sink the report would be cleared without going anywhere
+    if analytics.sinks.is_empty() {
+        return Err(CommandError::new(
+            "no_report_sink",
+            "No report sink is registered to submit usage reports to",
+        ));
+    }
+
+    let report = analytics.report();
+    for sink in &analytics.sinks {
+        sink.submit(&report)
+            .map_err(|message| CommandError::new("analytics_error", message))?;
+    }
+
+    analytics.clear();
+    save(&analytics)
+}
@@ -0,0 +1,67 @@
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use std::borrow::Cow;
+use std::sync::OnceLock;
+use tauri::{AppHandle, Manager};
+
+use crate::events;
+use crate::pdf;
+
+static APP: OnceLock<AppHandle> = OnceLock::new();
+
+/// Makes the app reachable from the `tauri` Python module. Called once from `setup`.
+pub fn init(app: AppHandle) {
+    let _ = APP.set(app);
+}
+
+fn app() -> PyResult<&'static AppHandle> {
+    APP.get()
+        .ok_or_else(|| PyRuntimeError::new_err("The Tauri app is not running yet"))
+}
+
+/// `tauri` module importable from Python, registered with `append_to_inittab!` before the
+/// interpreter starts. Only available inside the running app, so import it lazily.
+#[pymodule]
+#[pyo3(name = "tauri")]
+pub fn tauri_module(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(emit_message, m)?)?;
+    m.add_function(wrap_pyfunction!(print_window, m)?)?;
+    m.add_function(wrap_pyfunction!(render_pdf, m)?)?;
+    Ok(())
+}
+
+/// Emits an encoded protobuf message as a binary event; use `tauri_py.emit` instead of
+/// calling this directly.
+#[pyfunction]
+#[pyo3(signature = (event, message_type, data, window=None, invocation_id=None))]
+fn emit_message(
+    py: Python<'_>,
+    event: &str,
+    message_type: &str,
+    data: &[u8],
+    window: Option<&str>,
+    invocation_id: Option<&str>,
+) -> PyResult<()> {
+    let app = app()?;
+    py.allow_threads(|| events::emit_binary(app, event, message_type, data, window, invocation_id))
+        .map_err(|e| PyRuntimeError::new_err(e.to_string()))
+}
+
+/// Opens the print dialog of a window, as `tauri.print(window_label)`.
+#[pyfunction]
+#[pyo3(name = "print")]
+fn print_window(py: Python<'_>, window_label: &str) -> PyResult<()> {
+    let window = app()?
+        .get_window(window_label)
+        .ok_or_else(|| PyValueError::new_err(format!("No window labeled {}", window_label)))?;
+    py.allow_threads(|| window.print())
+        .map_err(|e| PyRuntimeError::new_err(e.to_string()))
+}
+
+/// Renders an HTML document to PDF, returned as `bytes`.
+#[pyfunction]
+fn render_pdf(py: Python<'_>, html: &str) -> PyResult<Cow<'static, [u8]>> {
+    py.allow_threads(|| pdf::render(html))
+        .map(Cow::Owned)
+        .map_err(PyRuntimeError::new_err)
+}
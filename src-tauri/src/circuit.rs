@@ -0,0 +1,344 @@
+use serde::Serialize;
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+use crate::dispatch::{CallContext, CommandError, Middleware, Next};
+
+/// Event emitted to all windows whenever a circuit changes state.
+pub const CIRCUIT_EVENT: &str = "circuit-breaker";
+
+/// Key used for the single circuit of [`CircuitBreaker::runtime`].
+const RUNTIME_CIRCUIT: &str = "*";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    Closed,
+    Open,
+    HalfOpen,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct CircuitEvent {
+    /// The command the circuit belongs to, or `None` for the runtime-wide circuit.
+    command: Option<String>,
+    state: CircuitState,
+    /// The call that caused the change; `None` when the cooldown ran out.
+    invocation_id: Option<String>,
+}
+
+struct Circuit {
+    state: CircuitState,
+    failures: u32,
+    /// Whether a half-open circuit already let its probe call through.
+    probing: bool,
+    reopens_at: Instant,
+    /// Bumped on every opening so a stale recovery timer can tell it is stale.
+    generation: u64,
+}
+
+impl Default for Circuit {
+    fn default() -> Self {
+        Circuit {
+            state: CircuitState::Closed,
+            failures: 0,
+            probing: false,
+            reopens_at: Instant::now(),
+            generation: 0,
+        }
+    }
+}
+
+impl Circuit {
+    /// Whether a call may go through, marking it as the probe of a half-open circuit.
+    fn admit(&mut self) -> bool {
+        match self.state {
+            CircuitState::Closed => true,
+            CircuitState::HalfOpen if !self.probing => {
+                self.probing = true;
+                true
+            }
+            CircuitState::Open | CircuitState::HalfOpen => false,
+        }
+    }
+
+    /// Records the outcome of an admitted call, returning the new state if it changed.
+    fn record(
+        &mut self,
+        success: bool,
+        threshold: u32,
+        cooldown: Duration,
+    ) -> Option<CircuitState> {
+        if success {
+            self.failures = 0;
+            if self.state == CircuitState::Closed {
+                return None;
+            }
+            self.state = CircuitState::Closed;
+            self.probing = false;
+            return Some(CircuitState::Closed);
+        }
+
+        self.failures += 1;
+        let reopen = self.state == CircuitState::HalfOpen;
+        if !reopen && (self.state != CircuitState::Closed || self.failures < threshold) {
+            return None;
+        }
+        self.state = CircuitState::Open;
+        self.probing = false;
+        self.reopens_at = Instant::now() + cooldown;
+        self.generation += 1;
+        Some(CircuitState::Open)
+    }
+
+    /// Goes half-open once the cooldown of the opening numbered `generation` is over, unless
+    /// the circuit changed since. Returns whether it did.
+    fn cooldown_elapsed(&mut self, generation: u64) -> bool {
+        if self.state != CircuitState::Open || self.generation != generation {
+            return false;
+        }
+        self.state = CircuitState::HalfOpen;
+        true
+    }
+}
+
+/// Middleware failing fast with a `circuit_open` error once a command (or, with
+/// [`CircuitBreaker::runtime`], any command) failed `threshold` times in a row.
+///
+/// After `cooldown` the circuit goes half-open and lets a single call through as a probe:
+/// if it succeeds the circuit closes again, otherwise it reopens for another cooldown.
+/// Every state change is emitted as a [`CIRCUIT_EVENT`].
+pub struct CircuitBreaker {
+    app: AppHandle,
+    per_command: bool,
+    threshold: u32,
+    cooldown: Duration,
+    circuits: Arc<Mutex<HashMap<String, Circuit>>>,
+}
+
+impl CircuitBreaker {
+    /// One circuit per command.
+    pub fn per_command(app: AppHandle, threshold: u32, cooldown: Duration) -> Self {
+        Self::new(app, true, threshold, cooldown)
+    }
+
+    /// One circuit shared by all commands, for when the Python runtime itself is failing.
+    pub fn runtime(app: AppHandle, threshold: u32, cooldown: Duration) -> Self {
+        Self::new(app, false, threshold, cooldown)
+    }
+
+    fn new(app: AppHandle, per_command: bool, threshold: u32, cooldown: Duration) -> Self {
+        CircuitBreaker {
+            app,
+            per_command,
+            threshold,
+            cooldown,
+            circuits: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    fn key<'a>(&self, ctx: &'a CallContext) -> &'a str {
+        if self.per_command {
+            &ctx.command
+        } else {
+            RUNTIME_CIRCUIT
+        }
+    }
+
+    /// Checks whether the call may go through, marking it as the probe of a half-open
+    /// circuit if needed.
+    fn admit(&self, key: &str, command: &str) -> Result<(), CommandError> {
+        let mut circuits = self.circuits.lock().unwrap();
+        let circuit = circuits.entry(key.to_string()).or_default();
+        if circuit.admit() {
+            return Ok(());
+        }
+
+        let retry_after = circuit.reopens_at.saturating_duration_since(Instant::now());
+        let mut err = CommandError::new(
+            "circuit_open",
+            format!("`{}` is failing repeatedly and is paused", command),
+        );
+        err.details.insert(
+            "circuit".to_string(),
+            json!({
+                "state": circuit.state,
+                "failures": circuit.failures,
+                "retry_after_ms": retry_after.as_millis() as u64,
+            }),
+        );
+        Err(err)
+    }
+
+    fn record(&self, key: &str, invocation_id: &str, success: bool) {
+        let mut circuits = self.circuits.lock().unwrap();
+        let circuit = circuits.entry(key.to_string()).or_default();
+
+        let Some(state) = circuit.record(success, self.threshold, self.cooldown) else {
+            return;
+        };
+        self.emit(key, state, Some(invocation_id));
+        if state == CircuitState::Open {
+            self.schedule_probe(key.to_string(), circuit.generation);
+        }
+    }
+
+    /// Moves the circuit to half-open once the cooldown is over, unless it changed since.
+    fn schedule_probe(&self, key: String, generation: u64) {
+        let app = self.app.clone();
+        let per_command = self.per_command;
+        let cooldown = self.cooldown;
+        let circuits = self.circuits.clone();
+
+        thread::spawn(move || {
+            thread::sleep(cooldown);
+
+            let mut circuits = circuits.lock().unwrap();
+            let Some(circuit) = circuits.get_mut(&key) else {
+                return;
+            };
+            if circuit.cooldown_elapsed(generation) {
+                emit(&app, per_command, &key, CircuitState::HalfOpen, None);
+            }
+        });
+    }
+
+    fn emit(&self, key: &str, state: CircuitState, invocation_id: Option<&str>) {
+        emit(&self.app, self.per_command, key, state, invocation_id);
+    }
+}
+
+impl Middleware for CircuitBreaker {
+    fn around(&self, ctx: &mut CallContext, next: Next<'_>) -> Result<(), CommandError> {
+        let key = self.key(ctx).to_string();
+        self.admit(&key, &ctx.command)?;
+
+        let result = next.run(ctx);
+        self.record(&key, &ctx.invocation_id, result.is_ok());
+        result
+    }
+}
+
+fn emit(
+    app: &AppHandle,
+    per_command: bool,
+    key: &str,
+    state: CircuitState,
+    invocation_id: Option<&str>,
+) {
+    let event = CircuitEvent {
+        command: per_command.then(|| key.to_string()),
+        state,
+        invocation_id: invocation_id.map(str::to_string),
+    };
+    let _ = app.emit_all(CIRCUIT_EVENT, event);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const THRESHOLD: u32 = 3;
+    const COOLDOWN: Duration = Duration::from_secs(30);
+
+    fn fail(circuit: &mut Circuit) -> Option<CircuitState> {
+        assert!(circuit.admit());
+        circuit.record(false, THRESHOLD, COOLDOWN)
+    }
+
+    fn open() -> Circuit {
+        let mut circuit = Circuit::default();
+        for _ in 0..THRESHOLD {
+            fail(&mut circuit);
+        }
+        assert_eq!(circuit.state, CircuitState::Open);
+        circuit
+    }
+
+    #[test]
+    fn opens_after_threshold_consecutive_failures() {
+        let mut circuit = Circuit::default();
+        assert_eq!(fail(&mut circuit), None);
+        assert_eq!(fail(&mut circuit), None);
+        assert_eq!(fail(&mut circuit), Some(CircuitState::Open));
+        assert!(!circuit.admit());
+        assert!(circuit.reopens_at > Instant::now());
+    }
+
+    #[test]
+    fn success_resets_the_failure_count() {
+        let mut circuit = Circuit::default();
+        fail(&mut circuit);
+        fail(&mut circuit);
+        assert!(circuit.admit());
+        assert_eq!(circuit.record(true, THRESHOLD, COOLDOWN), None);
+
+        assert_eq!(fail(&mut circuit), None);
+        assert_eq!(circuit.state, CircuitState::Closed);
+    }
+
+    #[test]
+    fn half_open_admits_a_single_probe() {
+        let mut circuit = open();
+        assert!(circuit.cooldown_elapsed(circuit.generation));
+        assert_eq!(circuit.state, CircuitState::HalfOpen);
+
+        assert!(circuit.admit());
+        assert!(!circuit.admit());
+    }
+
+    #[test]
+    fn successful_probe_closes() {
+        let mut circuit = open();
+        circuit.cooldown_elapsed(circuit.generation);
+
+        assert!(circuit.admit());
+        assert_eq!(
+            circuit.record(true, THRESHOLD, COOLDOWN),
+            Some(CircuitState::Closed)
+        );
+        assert_eq!(circuit.failures, 0);
+        assert!(circuit.admit());
+    }
+
+    #[test]
+    fn failed_probe_reopens() {
+        let mut circuit = open();
+        let generation = circuit.generation;
+        circuit.cooldown_elapsed(generation);
+
+        assert_eq!(fail(&mut circuit), Some(CircuitState::Open));
+        assert_eq!(circuit.generation, generation + 1);
+        assert!(!circuit.probing);
+        assert!(!circuit.admit());
+    }
+
+    #[test]
+    fn stale_cooldown_is_ignored() {
+        let mut circuit = open();
+        let stale = circuit.generation;
+        circuit.cooldown_elapsed(stale);
+        fail(&mut circuit);
+
+        // The timer of the first opening fires after the circuit reopened
+        assert!(!circuit.cooldown_elapsed(stale));
+        assert_eq!(circuit.state, CircuitState::Open);
+        assert!(circuit.cooldown_elapsed(circuit.generation));
+    }
+
+    #[test]
+    fn cooldown_does_not_reopen_a_closed_circuit() {
+        let mut circuit = open();
+        let generation = circuit.generation;
+        circuit.cooldown_elapsed(generation);
+        circuit.admit();
+        circuit.record(true, THRESHOLD, COOLDOWN);
+
+        assert!(!circuit.cooldown_elapsed(generation));
+        assert_eq!(circuit.state, CircuitState::Closed);
+    }
+}
@@ -0,0 +1,480 @@
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::cell::RefCell;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::State;
+use uuid::Uuid;
+
+/// Python module defining the `Context` class handed to functions taking a `ctx` parameter.
+const CONTEXT_MODULE: &str = "python.src.tauri_py";
+
+/// Error returned to the frontend by every generated command.
+#[derive(Debug, Clone, Serialize)]
+pub struct CommandError {
+    pub code: String,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exception: Option<String>,
+    /// Id of the invocation that failed, matching the one in logs and events.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub invocation_id: Option<String>,
+    /// Structured extras added along the way, e.g. retry metadata.
+    #[serde(skip_serializing_if = "Map::is_empty")]
+    pub details: Map<String, Value>,
+    /// The exception class followed by its bases, so errors can be matched by class.
+    #[serde(skip)]
+    pub exception_classes: Vec<String>,
+}
+
+impl CommandError {
+    pub fn new(code: impl Into<String>, message: impl Into<String>) -> Self {
+        CommandError {
+            code: code.into(),
+            message: message.into(),
+            exception: None,
+            invocation_id: None,
+            details: Map::new(),
+            exception_classes: Vec::new(),
+        }
+    }
+
+    /// Whether the error was raised by a Python exception of `class` or a subclass of it.
+    pub fn is_exception(&self, class: &str) -> bool {
+        self.exception_classes.iter().any(|c| c == class)
+    }
+
+    fn from_py(py: Python<'_>, err: &PyErr) -> Self {
+        let exception_classes = err
+            .get_type_bound(py)
+            .getattr("__mro__")
+            .and_then(|mro| {
+                mro.iter()?
+                    .map(|class| class?.getattr("__qualname__")?.extract())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        CommandError {
+            code: "python_error".to_string(),
+            message: err.to_string(),
+            exception: err.get_type_bound(py).qualname().ok(),
+            invocation_id: None,
+            details: Map::new(),
+            exception_classes,
+        }
+    }
+}
+
+/// Hooks run around every generated command, so projects can add auth checks, metrics or
+/// their own error format without touching the generator.
+///
+/// All methods have no-op defaults; implement only the ones you need and register the
+/// implementation with [`Dispatcher::hook`].
+pub trait CommandHooks: Send + Sync {
+    /// Runs before the Python function is called. Returning an error rejects the call and the
+    /// error is sent to the frontend as is.
+    fn pre_call(&self, _ctx: &CallContext) -> Result<(), CommandError> {
+        Ok(())
+    }
+
+    /// Runs once the call has finished, including calls rejected by a `pre_call` hook.
+    fn post_call(
+        &self,
+        _ctx: &CallContext,
+        _result: Result<(), &CommandError>,
+        _elapsed: Duration,
+    ) {
+    }
+
+    /// Turns a Python exception into the error sent to the frontend. Returning `None` leaves
+    /// it to the next hook, falling back to the exception message.
+    fn map_error(&self, _ctx: &CallContext, _py: Python<'_>, _err: &PyErr) -> Option<CommandError> {
+        None
+    }
+}
+
+impl<T: CommandHooks> CommandHooks for Arc<T> {
+    fn pre_call(&self, ctx: &CallContext) -> Result<(), CommandError> {
+        (**self).pre_call(ctx)
+    }
+
+    fn post_call(&self, ctx: &CallContext, result: Result<(), &CommandError>, elapsed: Duration) {
+        (**self).post_call(ctx, result, elapsed)
+    }
+
+    fn map_error(&self, ctx: &CallContext, py: Python<'_>, err: &PyErr) -> Option<CommandError> {
+        (**self).map_error(ctx, py, err)
+    }
+}
+
+/// Values the app sets once, e.g. after login, and every call carries from then on.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Session {
+    pub session_id: Option<String>,
+    pub user_id: Option<String>,
+    /// BCP 47 language tag; defaults to the system locale.
+    pub locale: Option<String>,
+}
+
+/// Everything known about a single command call. Hooks and middleware see it, and Python
+/// functions that take a `ctx` parameter get it as a `tauri_py.Context`.
+#[derive(Debug)]
+pub struct CallContext {
+    pub command: String,
+    /// Unique per call; included in errors, logs and emitted events for correlation.
+    pub invocation_id: String,
+    /// Label of the window that invoked the command.
+    pub window: String,
+    pub session_id: Option<String>,
+    pub user_id: Option<String>,
+    pub locale: Option<String>,
+    /// When the caller stops waiting for the result, if the dispatcher has a timeout.
+    pub deadline: Option<SystemTime>,
+    /// Free-form values middleware can attach for the Python side.
+    pub metadata: Map<String, Value>,
+}
+
+impl CallContext {
+    pub fn to_py<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        let kwargs = PyDict::new_bound(py);
+        kwargs.set_item("command", &self.command)?;
+        kwargs.set_item("invocation_id", &self.invocation_id)?;
+        kwargs.set_item("window", &self.window)?;
+        kwargs.set_item("session_id", &self.session_id)?;
+        kwargs.set_item("user_id", &self.user_id)?;
+        kwargs.set_item("locale", &self.locale)?;
+        kwargs.set_item(
+            "deadline",
+            self.deadline
+                .and_then(|deadline| deadline.duration_since(UNIX_EPOCH).ok())
+                .map(|deadline| deadline.as_secs_f64()),
+        )?;
+
+        let metadata = serde_json::to_string(&self.metadata).unwrap();
+        kwargs.set_item(
+            "metadata",
+            py.import_bound("json")?
+                .call_method1("loads", (metadata,))?,
+        )?;
+
+        py.import_bound(CONTEXT_MODULE)?
+            .getattr("Context")?
+            .call((), Some(&kwargs))
+    }
+}
+
+/// Wraps every generated command, for cross-cutting behavior like auth, logging or feature
+/// flags. `around` decides whether and how often to call `next`, and can replace or reshape
+/// the error on the way out.
+///
+/// Results stay typed per command, so a middleware that returns `Ok(())` without calling
+/// `next` gets a `not_executed` error sent to the frontend; short-circuit with an error
+/// instead.
+pub trait Middleware: Send + Sync {
+    fn around(&self, ctx: &mut CallContext, next: Next<'_>) -> Result<(), CommandError>;
+}
+
+impl<F> Middleware for F
+where
+    F: Fn(&mut CallContext, Next<'_>) -> Result<(), CommandError> + Send + Sync,
+{
+    fn around(&self, ctx: &mut CallContext, next: Next<'_>) -> Result<(), CommandError> {
+        self(ctx, next)
+    }
+}
+
+/// The rest of the middleware chain, ending in the Python call.
+#[derive(Clone, Copy)]
+pub struct Next<'a> {
+    middleware: &'a [Box<dyn Middleware>],
+    call: &'a dyn Fn(&CallContext) -> Result<(), CommandError>,
+}
+
+impl Next<'_> {
+    pub fn run(self, ctx: &mut CallContext) -> Result<(), CommandError> {
+        match self.middleware.split_first() {
+            Some((first, rest)) => first.around(
+                ctx,
+                Next {
+                    middleware: rest,
+                    call: self.call,
+                },
+            ),
+            None => (self.call)(ctx),
+        }
+    }
+}
+
+/// Runs the Python side of generated commands. Managed as Tauri state and taken by every
+/// generated command wrapper.
+pub struct Dispatcher {
+    hooks: Vec<Box<dyn CommandHooks>>,
+    middleware: Vec<Box<dyn Middleware>>,
+    session: RwLock<Session>,
+    timeout: Option<Duration>,
+}
+
+impl Default for Dispatcher {
+    fn default() -> Self {
+        Dispatcher {
+            hooks: Vec::new(),
+            middleware: Vec::new(),
+            session: RwLock::new(Session {
+                locale: system_locale(),
+                ..Session::default()
+            }),
+            timeout: None,
+        }
+    }
+}
+
+impl Dispatcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers hooks. Hooks run in registration order.
+    pub fn hook(mut self, hooks: impl CommandHooks + 'static) -> Self {
+        self.hooks.push(Box::new(hooks));
+        self
+    }
+
+    /// Adds a middleware to the chain. The first one registered is the outermost; all of
+    /// them run between the `pre_call` and `post_call` hooks.
+    pub fn middleware(mut self, middleware: impl Middleware + 'static) -> Self {
+        self.middleware.push(Box::new(middleware));
+        self
+    }
+
+    /// Gives every call a deadline `timeout` after it starts. The deadline is advisory: it is
+    /// passed to Python and respected by retries, but a running call is not interrupted.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn set_session(&self, mut session: Session) {
+        session.locale = session.locale.or_else(system_locale);
+        *self.session.write().unwrap() = session;
+    }
+
+    pub fn call<T>(
+        &self,
+        command: &str,
+        window: &str,
+        f: impl Fn(Python<'_>, &CallContext) -> PyResult<T>,
+    ) -> Result<T, CommandError> {
+        let started = Instant::now();
+        let mut ctx = self.context(command, window);
+
+        let output = RefCell::new(None);
+        let call = |ctx: &CallContext| -> Result<(), CommandError> {
+            let value =
+                Python::with_gil(|py| f(py, ctx).map_err(|err| self.map_error(ctx, py, &err)))?;
+            *output.borrow_mut() = Some(value);
+            Ok(())
+        };
+        let next = Next {
+            middleware: &self.middleware,
+            call: &call,
+        };
+
+        let result = self
+            .hooks
+            .iter()
+            .try_for_each(|hooks| hooks.pre_call(&ctx))
+            .and_then(|()| next.run(&mut ctx))
+            .and_then(|()| {
+                output.take().ok_or_else(|| {
+                    CommandError::new(
+                        "not_executed",
+                        format!("Middleware skipped `{}` without an error", command),
+                    )
+                })
+            })
+            .map_err(|mut err| {
+                err.invocation_id
+                    .get_or_insert_with(|| ctx.invocation_id.clone());
+                err
+            });
+
+        let elapsed = started.elapsed();
+        for hooks in &self.hooks {
+            hooks.post_call(&ctx, result.as_ref().map(|_| ()), elapsed);
+        }
+
+        match &result {
+            Ok(_) => log::debug!(
+                "{} [{}] succeeded in {:?}",
+                command,
+                ctx.invocation_id,
+                elapsed
+            ),
+            Err(err) => log::warn!(
+                "{} [{}] failed in {:?}: {} ({})",
+                command,
+                ctx.invocation_id,
+                elapsed,
+                err.message,
+                err.code
+            ),
+        }
+
+        result
+    }
+
+    fn context(&self, command: &str, window: &str) -> CallContext {
+        let session = self.session.read().unwrap().clone();
+        CallContext {
+            command: command.to_string(),
+            invocation_id: Uuid::new_v4().to_string(),
+            window: window.to_string(),
+            session_id: session.session_id,
+            user_id: session.user_id,
+            locale: session.locale,
+            deadline: self.timeout.map(|timeout| SystemTime::now() + timeout),
+            metadata: Map::new(),
+        }
+    }
+
+    fn map_error(&self, ctx: &CallContext, py: Python<'_>, err: &PyErr) -> CommandError {
+        self.hooks
+            .iter()
+            .find_map(|hooks| hooks.map_error(ctx, py, err))
+            .unwrap_or_else(|| CommandError::from_py(py, err))
+    }
+}
+
+/// The system locale from the usual POSIX variables, e.g. `en_US.UTF-8` becomes `en-US`.
+fn system_locale() -> Option<String> {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.is_empty() && value != "C" && value != "POSIX")
+        .map(|value| {
+            value
+                .split(['.', '@'])
+                .next()
+                .unwrap_or_default()
+                .replace('_', "-")
+        })
+}
+
+/// Sets the session id, user id and locale carried by every following call.
+#[tauri::command]
+pub fn set_session(dispatcher: State<'_, Dispatcher>, session: Session) {
+    dispatcher.set_session(session);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    type Log = Arc<Mutex<Vec<String>>>;
+
+    struct Recorder {
+        log: Log,
+        reject: bool,
+    }
+
+    impl CommandHooks for Recorder {
+        fn pre_call(&self, _ctx: &CallContext) -> Result<(), CommandError> {
+            self.log.lock().unwrap().push("pre_call".to_string());
+            if self.reject {
+                return Err(CommandError::new("denied", "Not allowed"));
+            }
+            Ok(())
+        }
+
+        fn post_call(&self, _ctx: &CallContext, result: Result<(), &CommandError>, _: Duration) {
+            let outcome = result.map_or_else(|err| err.code.clone(), |()| "ok".to_string());
+            self.log
+                .lock()
+                .unwrap()
+                .push(format!("post_call {}", outcome));
+        }
+    }
+
+    fn recording(log: &Log, name: &'static str) -> impl Middleware {
+        let log = log.clone();
+        move |ctx: &mut CallContext, next: Next<'_>| {
+            log.lock().unwrap().push(format!("{} before", name));
+            let result = next.run(ctx);
+            log.lock().unwrap().push(format!("{} after", name));
+            result
+        }
+    }
+
+    fn call(dispatcher: &Dispatcher, log: &Log) -> Result<u32, CommandError> {
+        dispatcher.call("greet", "main", |_, _| {
+            log.lock().unwrap().push("call".to_string());
+            Ok(1)
+        })
+    }
+
+    fn entries(log: &Log) -> Vec<String> {
+        log.lock().unwrap().clone()
+    }
+
+    #[test]
+    fn first_middleware_is_outermost_and_runs_between_hooks() {
+        let log = Log::default();
+        let dispatcher = Dispatcher::new()
+            .hook(Recorder {
+                log: log.clone(),
+                reject: false,
+            })
+            .middleware(recording(&log, "outer"))
+            .middleware(recording(&log, "inner"));
+
+        assert_eq!(call(&dispatcher, &log).unwrap(), 1);
+        assert_eq!(
+            entries(&log),
+            [
+                "pre_call",
+                "outer before",
+                "inner before",
+                "call",
+                "inner after",
+                "outer after",
+                "post_call ok",
+            ]
+        );
+    }
+
+    #[test]
+    fn skipping_next_without_an_error_is_not_executed() {
+        let log = Log::default();
+        let dispatcher = Dispatcher::new()
+            .hook(Recorder {
+                log: log.clone(),
+                reject: false,
+            })
+            .middleware(|_: &mut CallContext, _: Next<'_>| -> Result<(), CommandError> { Ok(()) });
+
+        let err = call(&dispatcher, &log).unwrap_err();
+        assert_eq!(err.code, "not_executed");
+        assert!(err.invocation_id.is_some());
+        assert_eq!(entries(&log), ["pre_call", "post_call not_executed"]);
+    }
+
+    #[test]
+    fn pre_call_rejection_skips_middleware_but_not_post_call() {
+        let log = Log::default();
+        let dispatcher = Dispatcher::new()
+            .hook(Recorder {
+                log: log.clone(),
+                reject: true,
+            })
+            .middleware(recording(&log, "outer"));
+
+        let err = call(&dispatcher, &log).unwrap_err();
+        assert_eq!(err.code, "denied");
+        assert_eq!(entries(&log), ["pre_call", "post_call denied"]);
+    }
+}
@@ -0,0 +1,39 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+
+/// Payload of an event carrying a protobuf message, decoded on the frontend by
+/// `decodeEvent` in `src/events.ts`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BinaryEvent {
+    /// Full name of the protobuf message type, e.g. `TaskProgress`.
+    pub message_type: String,
+    /// Base64 encoded message; Tauri events are JSON, so raw bytes would be sent as an
+    /// array of numbers.
+    pub data: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub invocation_id: Option<String>,
+}
+
+/// Emits an encoded protobuf message to `window`, or to all windows if `None`.
+pub fn emit_binary(
+    app: &AppHandle,
+    event: &str,
+    message_type: &str,
+    data: &[u8],
+    window: Option<&str>,
+    invocation_id: Option<&str>,
+) -> tauri::Result<()> {
+    let payload = BinaryEvent {
+        message_type: message_type.to_string(),
+        data: STANDARD.encode(data),
+        invocation_id: invocation_id.map(str::to_string),
+    };
+
+    match window {
+        Some(label) => app.emit_to(label, event, payload),
+        None => app.emit_all(event, payload),
+    }
+}
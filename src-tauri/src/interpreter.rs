@@ -0,0 +1,269 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tauri::AppHandle;
+
+use crate::dispatch::CommandError;
+
+const SETTINGS_FILE: &str = "python.json";
+
+/// The `major.minor` version of the Python library the app is linked against.
+const LINKED_PYTHON: &str = env!("TAURI_PY_PYTHON_VERSION");
+
+/// Whether the app links the version-agnostic stable ABI library (Windows `abi3` builds), so
+/// any Python from [`LINKED_PYTHON`] on can run it.
+const STABLE_ABI: &str = env!("TAURI_PY_PYTHON_STABLE_ABI");
+
+const PROBE_SCRIPT: &str = r#"
+import json, sys, sysconfig
+paths = sysconfig.get_paths()
+print(json.dumps({
+    "version": list(sys.version_info[:3]),
+    "prefix": sys.prefix,
+    "base_prefix": sys.base_prefix,
+    "site_packages": sorted({paths["purelib"], paths["platlib"]}),
+}))
+"#;
+
+/// A Python interpreter found on the machine, as shown in the settings UI.
+#[derive(Debug, Serialize)]
+pub struct PythonInterpreter {
+    pub path: String,
+    pub version: String,
+    pub prefix: String,
+    /// Whether the app can run with its version of Python.
+    pub compatible: bool,
+    pub selected: bool,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct Settings {
+    interpreter: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Probe {
+    version: (u8, u8, u8),
+    prefix: String,
+    base_prefix: String,
+    site_packages: Vec<String>,
+}
+
+/// Points the Python runtime at the interpreter selected in the settings. Python reads its
+/// environment on initialization, so this has to run before the runtime is first used and
+/// only takes effect on the next start after a change.
+pub fn configure(config: &tauri::Config) {
+    let Some(interpreter) =
+        tauri::api::path::app_config_dir(config).and_then(|dir| load_settings(&dir).interpreter)
+    else {
+        return;
+    };
+
+    // A selection that no longer works, or was upgraded to a Python the app isn't linked
+    // against, falls back to the default environment; otherwise Python would fail to start
+    // and the settings could never be changed back
+    let Ok(probe) = probe(Path::new(&interpreter)) else {
+        return;
+    };
+    if !is_compatible(probe.version) {
+        return;
+    }
+
+    std::env::set_var("PYTHONHOME", &probe.base_prefix);
+
+    let mut python_path: Vec<PathBuf> = std::env::var_os("PYTHONPATH")
+        .map(|paths| std::env::split_paths(&paths).collect())
+        .unwrap_or_default();
+    python_path.extend(probe.site_packages.iter().map(PathBuf::from));
+    if let Ok(joined) = std::env::join_paths(python_path) {
+        std::env::set_var("PYTHONPATH", joined);
+    }
+}
+
+/// Runs every candidate interpreter to probe it, so this runs off the main thread.
+#[tauri::command(async)]
+pub fn list_python_interpreters(app: AppHandle) -> Result<Vec<PythonInterpreter>, CommandError> {
+    let selected = selected_interpreter(&app).map(PathBuf::from);
+
+    let mut seen = HashSet::new();
+    let interpreters = candidates()
+        .into_iter()
+        .chain(selected.clone())
+        .filter(|path| seen.insert(absolute(path)))
+        .filter_map(|path| {
+            let probe = probe(&path).ok()?;
+            Some(PythonInterpreter {
+                compatible: is_compatible(probe.version),
+                selected: selected.as_deref().map(absolute) == Some(absolute(&path)),
+                version: format!(
+                    "{}.{}.{}",
+                    probe.version.0, probe.version.1, probe.version.2
+                ),
+                prefix: probe.prefix,
+                path: path.to_string_lossy().into_owned(),
+            })
+        })
+        .collect();
+
+    Ok(interpreters)
+}
+
+/// Selects the interpreter to use from the next start on; `None` goes back to the default
+/// environment. With `restart` set the app restarts right away to apply the change. Probes
+/// the interpreter first, so this runs off the main thread.
+#[tauri::command(async)]
+pub fn set_python_interpreter(
+    app: AppHandle,
+    path: Option<String>,
+    restart: bool,
+) -> Result<(), CommandError> {
+    if let Some(path) = &path {
+        let probe = probe(Path::new(path))
+            .map_err(|message| CommandError::new("invalid_interpreter", message))?;
+
+        if !is_compatible(probe.version) {
+            return Err(CommandError::new(
+                "invalid_interpreter",
+                format!(
+                    "{} is Python {}.{}, but the app requires Python {}",
+                    path,
+                    probe.version.0,
+                    probe.version.1,
+                    required_python()
+                ),
+            ));
+        }
+    }
+
+    let dir = app
+        .path_resolver()
+        .app_config_dir()
+        .ok_or_else(|| CommandError::new("settings_error", "No app config directory"))?;
+    save_settings(&dir, &Settings { interpreter: path })
+        .map_err(|e| CommandError::new("settings_error", e.to_string()))?;
+
+    if restart {
+        app.restart();
+    }
+
+    Ok(())
+}
+
+fn selected_interpreter(app: &AppHandle) -> Option<String> {
+    app.path_resolver()
+        .app_config_dir()
+        .and_then(|dir| load_settings(&dir).interpreter)
+}
+
+/// Compares paths without resolving symlinks: a virtualenv's `bin/python` links to the base
+/// interpreter but runs with a different `sys.prefix`, so both are listed separately.
+fn absolute(path: &Path) -> PathBuf {
+    std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+fn linked_version() -> (u8, u8) {
+    let (major, minor) = LINKED_PYTHON.split_once('.').unwrap();
+    (major.parse().unwrap(), minor.parse().unwrap())
+}
+
+/// Whether an interpreter of `version` (major, minor, patch) can run the app.
+fn is_compatible(version: (u8, u8, u8)) -> bool {
+    let linked = linked_version();
+    if STABLE_ABI == "true" {
+        version.0 == linked.0 && version.1 >= linked.1
+    } else {
+        (version.0, version.1) == linked
+    }
+}
+
+fn required_python() -> String {
+    if STABLE_ABI == "true" {
+        format!("{} or newer", LINKED_PYTHON)
+    } else {
+        LINKED_PYTHON.to_string()
+    }
+}
+
+fn load_settings(dir: &Path) -> Settings {
+    fs::read_to_string(dir.join(SETTINGS_FILE))
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+fn save_settings(dir: &Path, settings: &Settings) -> std::io::Result<()> {
+    fs::create_dir_all(dir)?;
+    fs::write(
+        dir.join(SETTINGS_FILE),
+        serde_json::to_vec_pretty(settings)?,
+    )
+}
+
+/// Runs the interpreter to find out its version and where its packages live.
+fn probe(path: &Path) -> Result<Probe, String> {
+    let output = Command::new(path)
+        .arg("-c")
+        .arg(PROBE_SCRIPT)
+        .output()
+        .map_err(|e| format!("Failed to run {}: {}", path.display(), e))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "{} is not a working Python interpreter: {}",
+            path.display(),
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    serde_json::from_slice(&output.stdout)
+        .map_err(|e| format!("Unexpected output from {}: {}", path.display(), e))
+}
+
+/// Interpreters on `PATH` plus the active virtualenv or conda environment, if any.
+fn candidates() -> Vec<PathBuf> {
+    let bin = if cfg!(windows) { "Scripts" } else { "bin" };
+    let exe = if cfg!(windows) {
+        "python.exe"
+    } else {
+        "python"
+    };
+
+    let mut candidates: Vec<PathBuf> = ["VIRTUAL_ENV", "CONDA_PREFIX"]
+        .iter()
+        .filter_map(std::env::var_os)
+        .map(|prefix| PathBuf::from(prefix).join(bin).join(exe))
+        .filter(|path| path.is_file())
+        .collect();
+
+    let Some(paths) = std::env::var_os("PATH") else {
+        return candidates;
+    };
+    for dir in std::env::split_paths(&paths) {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        let mut found: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                let name = path.file_name().unwrap_or_default().to_string_lossy();
+                let name = name.strip_suffix(".exe").unwrap_or(&name);
+                name == "python"
+                    || name
+                        .strip_prefix("python3")
+                        .is_some_and(|rest| rest.is_empty() || is_minor_suffix(rest))
+            })
+            .collect();
+        found.sort();
+        candidates.extend(found);
+    }
+
+    candidates
+}
+
+/// Matches the `.12` in `python3.12`.
+fn is_minor_suffix(rest: &str) -> bool {
+    rest.strip_prefix('.')
+        .is_some_and(|minor| !minor.is_empty() && minor.chars().all(|c| c.is_ascii_digit()))
+}
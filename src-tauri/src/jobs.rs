@@ -0,0 +1,376 @@
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::cell::RefCell;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager, State, Window};
+use uuid::Uuid;
+
+use crate::dispatch::{CallContext, CommandError, Dispatcher};
+use crate::gen::py_commands::COMMANDS;
+use crate::gen::state::state::{Job, JobQueue, JobStatus};
+use crate::persist;
+
+/// Module the job's command is looked up in, the same one commands are generated from.
+const COMMANDS_MODULE: &str = "python.src";
+
+const JOBS_FILE: &str = "jobs.pb";
+
+/// Event emitted to the submitting window when a job starts and when it finishes.
+pub const JOB_EVENT: &str = "job";
+
+/// A job as shown to the frontend.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobInfo {
+    pub id: String,
+    pub command: String,
+    pub args: Value,
+    /// `queued`, `running` or `interrupted`.
+    pub status: &'static str,
+    pub queued_at: i64,
+    pub window: String,
+}
+
+impl From<&Job> for JobInfo {
+    fn from(job: &Job) -> Self {
+        JobInfo {
+            id: job.id.clone(),
+            command: job.command.clone(),
+            args: serde_json::from_str(&job.args_json).unwrap_or_default(),
+            status: match job.status.enum_value_or_default() {
+                JobStatus::JOB_STATUS_QUEUED => "queued",
+                JobStatus::JOB_STATUS_RUNNING => "running",
+                JobStatus::JOB_STATUS_INTERRUPTED => "interrupted",
+            },
+            queued_at: job.queued_at,
+            window: job.window.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct JobEvent {
+    id: String,
+    command: String,
+    /// `running`, `succeeded` or `failed`.
+    status: &'static str,
+    /// Id of the dispatcher call running the job, matching the one in logs and errors.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    invocation_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<CommandError>,
+}
+
+/// The unfinished jobs, saved to disk after every change.
+struct Store {
+    path: Option<PathBuf>,
+    queue: Mutex<JobQueue>,
+}
+
+impl Store {
+    fn update<T>(&self, f: impl FnOnce(&mut JobQueue) -> T) -> T {
+        let mut queue = self.queue.lock().unwrap();
+        let result = f(&mut queue);
+        if let Some(path) = &self.path {
+            if let Err(e) = persist::save(path, &*queue) {
+                log::error!("Failed to save the job queue: {}", e);
+            }
+        }
+        result
+    }
+}
+
+/// Runs Python functions one at a time on a background thread. Queued and running jobs are
+/// persisted, so jobs cut off by the app exiting show up as interrupted on the next launch
+/// and can be resumed or discarded.
+pub struct JobManager {
+    store: Arc<Store>,
+    sender: Mutex<Sender<String>>,
+    commands: Vec<&'static str>,
+}
+
+impl JobManager {
+    /// Loads the saved queue from `dir`, marks everything in it as interrupted and starts the
+    /// worker. Only the generated commands listed in `commands` can run as jobs. Needs the
+    /// [`Dispatcher`] to be managed already.
+    pub fn start(app: AppHandle, dir: Option<PathBuf>, commands: &[&'static str]) -> Self {
+        for command in commands {
+            assert!(
+                COMMANDS.contains(command),
+                "`{}` is not a generated command",
+                command
+            );
+        }
+
+        let path = dir.map(|dir| dir.join(JOBS_FILE));
+        let mut queue: JobQueue = path.as_deref().map(persist::load).unwrap_or_default();
+        for job in &mut queue.jobs {
+            job.status = JobStatus::JOB_STATUS_INTERRUPTED.into();
+        }
+
+        let store = Arc::new(Store {
+            path,
+            queue: Mutex::new(queue),
+        });
+        store.update(|_| ());
+
+        let (sender, receiver) = mpsc::channel();
+        let worker_store = store.clone();
+        thread::spawn(move || work(app, worker_store, receiver));
+
+        JobManager {
+            store,
+            sender: Mutex::new(sender),
+            commands: commands.to_vec(),
+        }
+    }
+
+    /// Keeps the frontend from running anything but the allowed commands, like Python
+    /// functions that aren't registered as commands or were imported into the package.
+    fn check_command(&self, command: &str) -> Result<(), CommandError> {
+        if self.commands.contains(&command) {
+            Ok(())
+        } else {
+            Err(CommandError::new(
+                "unknown_command",
+                format!("`{}` can't run as a background job", command),
+            ))
+        }
+    }
+
+    fn enqueue(&self, id: String) {
+        let _ = self.sender.lock().unwrap().send(id);
+    }
+}
+
+fn work(app: AppHandle, store: Arc<Store>, receiver: Receiver<String>) {
+    for id in receiver {
+        // Discarded while waiting
+        let Some(job) = store.update(|queue| {
+            let job = queue.jobs.iter_mut().find(|job| job.id == id)?;
+            job.status = JobStatus::JOB_STATUS_RUNNING.into();
+            Some(job.clone())
+        }) else {
+            continue;
+        };
+
+        let dispatcher = app.state::<Dispatcher>();
+        let invocation_id = RefCell::new(None);
+        let result = dispatcher
+            .call(&job.command, &job.window, |py, ctx| {
+                // Retries are part of the same invocation, so only the first attempt announces it
+                if invocation_id.borrow().is_none() {
+                    *invocation_id.borrow_mut() = Some(ctx.invocation_id.clone());
+                    py.allow_threads(|| {
+                        emit(&app, &job, "running", Some(&ctx.invocation_id), None, None)
+                    });
+                }
+                run_python(py, &job, ctx)
+            })
+            .and_then(|result| to_json(result, invocation_id.borrow().clone()));
+
+        store.update(|queue| queue.jobs.retain(|queued| queued.id != job.id));
+
+        match result {
+            Ok(value) => emit(
+                &app,
+                &job,
+                "succeeded",
+                invocation_id.borrow().as_deref(),
+                Some(value),
+                None,
+            ),
+            Err(err) => emit(
+                &app,
+                &job,
+                "failed",
+                err.invocation_id.clone().as_deref(),
+                None,
+                Some(err),
+            ),
+        }
+    }
+}
+
+/// Calls the job's function with its arguments, plus the call context if the function takes
+/// a `ctx` parameter. Like with commands, arguments are named in camelCase, so `file_path`
+/// is passed as `filePath`.
+fn run_python(py: Python<'_>, job: &Job, ctx: &CallContext) -> PyResult<Py<PyAny>> {
+    let json = py.import_bound("json")?;
+    let func = py
+        .import_bound(COMMANDS_MODULE)?
+        .getattr(job.command.as_str())?;
+
+    let args: Map<String, Value> = serde_json::from_str(&job.args_json).unwrap_or_default();
+    let kwargs = PyDict::new_bound(py);
+    let parameters = py
+        .import_bound("inspect")?
+        .call_method1("signature", (&func,))?
+        .getattr("parameters")?;
+    for name in parameters.iter()? {
+        let name: String = name?.extract()?;
+        if name == "ctx" {
+            kwargs.set_item("ctx", ctx.to_py(py)?)?;
+        } else if let Some(value) = args.get(&to_camel_case(&name)) {
+            kwargs.set_item(&name, json.call_method1("loads", (value.to_string(),))?)?;
+        }
+    }
+
+    Ok(func.call((), Some(&kwargs))?.unbind())
+}
+
+/// Encodes the job's result as JSON. Done after the dispatcher call, so a result that can't
+/// be encoded fails the job without being retried or counted as a Python failure.
+fn to_json(result: Py<PyAny>, invocation_id: Option<String>) -> Result<Value, CommandError> {
+    Python::with_gil(|py| -> PyResult<Value> {
+        let encoded: String = py
+            .import_bound("json")?
+            .call_method1("dumps", (result.bind(py),))?
+            .extract()?;
+        serde_json::from_str(&encoded).map_err(|e| PyValueError::new_err(e.to_string()))
+    })
+    .map_err(|err| {
+        let mut err = CommandError::new(
+            "invalid_result",
+            format!("The job's result can't be sent as JSON: {}", err),
+        );
+        err.invocation_id = invocation_id;
+        err
+    })
+}
+
+fn emit(
+    app: &AppHandle,
+    job: &Job,
+    status: &'static str,
+    invocation_id: Option<&str>,
+    result: Option<Value>,
+    error: Option<CommandError>,
+) {
+    let event = JobEvent {
+        id: job.id.clone(),
+        command: job.command.clone(),
+        status,
+        invocation_id: invocation_id.map(str::to_string),
+        result,
+        error,
+    };
+    let _ = app.emit_to(&job.window, JOB_EVENT, event);
+}
+
+/// The argument name Tauri uses for a snake case parameter, e.g. `filePath` for `file_path`.
+fn to_camel_case(name: &str) -> String {
+    let mut camel = String::new();
+    let mut upper = false;
+    for c in name.trim_start_matches('_').chars() {
+        if c == '_' {
+            upper = true;
+        } else if upper {
+            camel.extend(c.to_uppercase());
+            upper = false;
+        } else {
+            camel.push(c);
+        }
+    }
+    camel
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+/// Queues a call of the generated command `command` with `args`, named in camelCase as when
+/// invoking the command, returning the job id. Progress is reported through [`JOB_EVENT`].
+#[tauri::command]
+pub fn submit_job(
+    jobs: State<'_, JobManager>,
+    window: Window,
+    command: String,
+    args: Option<Map<String, Value>>,
+) -> Result<String, CommandError> {
+    jobs.check_command(&command)?;
+
+    let job = Job {
+        id: Uuid::new_v4().to_string(),
+        command,
+        args_json: Value::Object(args.unwrap_or_default()).to_string(),
+        status: JobStatus::JOB_STATUS_QUEUED.into(),
+        queued_at: now(),
+        window: window.label().to_string(),
+        ..Job::default()
+    };
+    let id = job.id.clone();
+
+    jobs.store.update(|queue| queue.jobs.push(job));
+    jobs.enqueue(id.clone());
+    Ok(id)
+}
+
+/// Lists unfinished jobs, including the ones interrupted by the last exit.
+#[tauri::command]
+pub fn list_jobs(jobs: State<'_, JobManager>) -> Vec<JobInfo> {
+    jobs.store
+        .queue
+        .lock()
+        .unwrap()
+        .jobs
+        .iter()
+        .map(JobInfo::from)
+        .collect()
+}
+
+/// Queues an interrupted job again. Jobs that were running start over from the beginning.
+#[tauri::command]
+pub fn resume_job(jobs: State<'_, JobManager>, id: String) -> Result<(), CommandError> {
+    let resumed = jobs.store.update(|queue| {
+        let job = queue.jobs.iter_mut().find(|job| {
+            job.id == id && job.status.enum_value_or_default() == JobStatus::JOB_STATUS_INTERRUPTED
+        });
+        let Some(job) = job else {
+            return Err(CommandError::new(
+                "job_not_found",
+                format!("No interrupted job with id {}", id),
+            ));
+        };
+        // The saved queue may come from a version that allowed other commands
+        jobs.check_command(&job.command)?;
+        job.status = JobStatus::JOB_STATUS_QUEUED.into();
+        Ok(())
+    });
+
+    resumed?;
+    jobs.enqueue(id);
+    Ok(())
+}
+
+/// Drops a queued or interrupted job.
+#[tauri::command]
+pub fn discard_job(jobs: State<'_, JobManager>, id: String) -> Result<(), CommandError> {
+    jobs.store
+        .update(|queue| {
+            let index = queue.jobs.iter().position(|job| {
+                job.id == id && job.status.enum_value_or_default() != JobStatus::JOB_STATUS_RUNNING
+            })?;
+            queue.jobs.remove(index);
+            Some(())
+        })
+        .ok_or_else(|| {
+            CommandError::new(
+                "job_not_found",
+                format!("No queued or interrupted job with id {}", id),
+            )
+        })
+}
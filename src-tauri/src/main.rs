@@ -1,22 +1,92 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
+mod analytics;
+mod bridge;
+mod circuit;
+mod dispatch;
+mod events;
 mod gen;
+mod interpreter;
+mod jobs;
+mod pdf;
+mod persist;
+mod retry;
 
-use pyo3::prelude::*;
-use std::sync::Mutex;
-use tauri::{Builder, Manager};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{Builder, Manager, RunEvent};
 
+use analytics::{
+    clear_usage_report, get_analytics_enabled, get_usage_report, set_analytics_enabled,
+    submit_usage_report, Analytics,
+};
+use bridge::tauri_module;
+use circuit::CircuitBreaker;
+use dispatch::{set_session, Dispatcher};
 use gen::{py_commands::*, state::state::AppState};
+use interpreter::{list_python_interpreters, set_python_interpreter};
+use jobs::{discard_job, list_jobs, resume_job, submit_job, JobManager};
+use retry::Retry;
 
 fn main() {
-    // Initialize Python environment here
-    Python::with_gil(|_| {
-        Builder::default()
-            .invoke_handler(tauri::generate_handler![greet, sum])
-            .setup(|app| {
-                app.manage(Mutex::new(AppState::default()));
-                Ok(())
-            })
-            .run(tauri::generate_context!())
-            .expect("error while running tauri application");
-    });
+    // Dispatcher and job logs go to stderr; `RUST_LOG=debug` also logs successful calls
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+
+    let context = tauri::generate_context!();
+    interpreter::configure(context.config());
+
+    pyo3::append_to_inittab!(tauri_module);
+
+    // Initialize Python environment here, releasing the GIL so commands and the job
+    // worker can each take it when they call into Python
+    pyo3::prepare_freethreaded_python();
+
+    Builder::default()
+        .invoke_handler(tauri::generate_handler![
+            greet,
+            sum,
+            list_python_interpreters,
+            set_python_interpreter,
+            set_session,
+            get_analytics_enabled,
+            set_analytics_enabled,
+            get_usage_report,
+            clear_usage_report,
+            submit_usage_report,
+            submit_job,
+            list_jobs,
+            resume_job,
+            discard_job
+        ])
+        .setup(|app| {
+            bridge::init(app.handle());
+            app.manage(Mutex::new(AppState::default()));
+
+            let analytics = Arc::new(Analytics::load(app.path_resolver().app_data_dir()));
+            app.manage(
+                Dispatcher::new()
+                    .hook(analytics.clone())
+                    .middleware(CircuitBreaker::per_command(
+                        app.handle(),
+                        5,
+                        Duration::from_secs(30),
+                    ))
+                    .middleware(Retry::new(retry_policies())),
+            );
+            app.manage(analytics);
+
+            // Python commands that may also run as background jobs
+            app.manage(JobManager::start(
+                app.handle(),
+                app.path_resolver().app_data_dir(),
+                &["greet", "sum"],
+            ));
+            Ok(())
+        })
+        .build(context)
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            if let RunEvent::Exit = event {
+                let _ = app.state::<Arc<Analytics>>().save();
+            }
+        });
 }
@@ -0,0 +1,138 @@
+use std::fs;
+use std::io::{self, Read};
+use std::path::PathBuf;
+use std::process::{Command, ExitStatus, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+/// Overrides the browser used to render PDFs.
+const BROWSER_ENV: &str = "TAURI_PY_PDF_BROWSER";
+
+/// How long the browser gets to render a PDF before it is killed.
+const RENDER_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Chromium based browsers that can print to PDF headlessly, by platform.
+#[cfg(target_os = "macos")]
+const BROWSERS: &[&str] = &[
+    "/Applications/Google Chrome.app/Contents/MacOS/Google Chrome",
+    "/Applications/Chromium.app/Contents/MacOS/Chromium",
+    "/Applications/Microsoft Edge.app/Contents/MacOS/Microsoft Edge",
+];
+#[cfg(target_os = "windows")]
+const BROWSERS: &[&str] = &[
+    r"C:\Program Files (x86)\Microsoft\Edge\Application\msedge.exe",
+    r"C:\Program Files\Microsoft\Edge\Application\msedge.exe",
+    r"C:\Program Files\Google\Chrome\Application\chrome.exe",
+];
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+const BROWSERS: &[&str] = &[
+    "chromium",
+    "chromium-browser",
+    "google-chrome",
+    "google-chrome-stable",
+    "microsoft-edge",
+];
+
+/// Renders an HTML document to PDF bytes with a headless Chromium based browser. The
+/// webview can't print to a file on every platform, so this doesn't go through it and
+/// requires such a browser to be installed. Windows always has Edge; on Linux and macOS
+/// Chrome, Chromium or Edge has to be installed separately.
+pub fn render(html: &str) -> Result<Vec<u8>, String> {
+    let browser = find_browser().ok_or_else(|| {
+        format!(
+            "Rendering PDFs requires Chrome, Chromium or Edge; install one or set {} to a Chromium based browser",
+            BROWSER_ENV
+        )
+    })?;
+
+    let id = Uuid::new_v4();
+    let input = std::env::temp_dir().join(format!("tauri-py-{}.html", id));
+    let output = std::env::temp_dir().join(format!("tauri-py-{}.pdf", id));
+    // A profile of its own keeps the browser from handing off to, or waiting on, an
+    // instance the user already has open
+    let profile = std::env::temp_dir().join(format!("tauri-py-{}-profile", id));
+    // Builds a proper `file:///C:/...` URL on Windows and escapes `#` or `%` in the path
+    let url = tauri::Url::from_file_path(&input)
+        .map_err(|()| format!("Failed to build a URL for {}", input.display()))?;
+    fs::write(&input, html).map_err(|e| format!("Failed to write the HTML: {}", e))?;
+
+    let mut command = Command::new(&browser);
+    command
+        .arg("--headless")
+        .arg("--disable-gpu")
+        .arg("--no-pdf-header-footer")
+        .arg(format!("--user-data-dir={}", profile.display()))
+        .arg(format!("--print-to-pdf={}", output.display()))
+        .arg(url.as_str());
+    let result = run(&mut command, RENDER_TIMEOUT)
+        .map_err(|e| format!("Failed to run {}: {}", browser.display(), e))
+        .and_then(|(status, stderr)| {
+            if status.success() {
+                fs::read(&output).map_err(|e| format!("Failed to read the PDF: {}", e))
+            } else {
+                Err(format!(
+                    "{} failed to render the PDF: {}",
+                    browser.display(),
+                    stderr
+                ))
+            }
+        });
+
+    let _ = fs::remove_file(&input);
+    let _ = fs::remove_file(&output);
+    let _ = fs::remove_dir_all(&profile);
+    result
+}
+
+/// Runs `command` to completion, killing it once `timeout` has passed. Returns its exit
+/// status and what it wrote to stderr.
+fn run(command: &mut Command, timeout: Duration) -> io::Result<(ExitStatus, String)> {
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    // Drained on a thread so a chatty browser can't fill the pipe and stall
+    let mut pipe = child.stderr.take().unwrap();
+    let stderr = thread::spawn(move || {
+        let mut stderr = String::new();
+        let _ = pipe.read_to_string(&mut stderr);
+        stderr
+    });
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                format!("gave up after {} seconds", timeout.as_secs()),
+            ));
+        }
+        thread::sleep(Duration::from_millis(50));
+    };
+
+    Ok((status, stderr.join().unwrap_or_default()))
+}
+
+fn find_browser() -> Option<PathBuf> {
+    if let Some(browser) = std::env::var_os(BROWSER_ENV) {
+        return Some(PathBuf::from(browser));
+    }
+
+    BROWSERS.iter().find_map(|browser| {
+        let path = PathBuf::from(browser);
+        if path.is_absolute() {
+            return path.is_file().then_some(path);
+        }
+        std::env::split_paths(&std::env::var_os("PATH")?)
+            .map(|dir| dir.join(browser))
+            .find(|candidate| candidate.is_file())
+    })
+}
@@ -0,0 +1,28 @@
+use protobuf::Message;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Reads a message saved with [`save`], falling back to the default message if the file is
+/// missing or unreadable.
+pub fn load<M: Message>(path: &Path) -> M {
+    fs::read(path)
+        .ok()
+        .and_then(|bytes| M::parse_from_bytes(&bytes).ok())
+        .unwrap_or_default()
+}
+
+/// Saves a message in its protobuf encoding. Writes to a temporary file first so a crash
+/// mid-write never leaves a truncated file behind.
+pub fn save<M: Message>(path: &Path, message: &M) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let bytes = message
+        .write_to_bytes()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, bytes)?;
+    fs::rename(tmp, path)
+}
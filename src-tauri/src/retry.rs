@@ -0,0 +1,243 @@
+use serde_json::json;
+use std::collections::HashMap;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::dispatch::{CallContext, CommandError, Middleware, Next};
+
+/// How a command is retried when its Python function raises. Set per function with the
+/// `tauri_py.retry` decorator, or per command with [`Retry::policy`].
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Total number of calls, including the first one.
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+    /// Factor the backoff grows by after every failed attempt.
+    pub multiplier: f64,
+    pub max_backoff: Duration,
+    /// Exception class names to retry on, matching subclasses too. Empty retries on any
+    /// Python exception.
+    pub retry_on: Vec<String>,
+}
+
+impl RetryPolicy {
+    fn retries(&self, err: &CommandError) -> bool {
+        err.exception.is_some()
+            && (self.retry_on.is_empty() || self.retry_on.iter().any(|c| err.is_exception(c)))
+    }
+
+    /// The wait after the `attempt`th failed call. Computed in floating point and clamped
+    /// before converting, since the unclamped backoff easily overflows a `Duration`.
+    fn backoff(&self, attempt: u32) -> Duration {
+        let exponent = i32::try_from(attempt.saturating_sub(1)).unwrap_or(i32::MAX);
+        let secs = self.initial_backoff.as_secs_f64() * self.multiplier.powi(exponent);
+        Duration::try_from_secs_f64(secs.min(self.max_backoff.as_secs_f64()))
+            .unwrap_or(self.max_backoff)
+    }
+}
+
+/// Middleware retrying commands that have a [`RetryPolicy`]. Only Python exceptions are
+/// retried; errors from other middleware or hooks are passed through.
+///
+/// The backoff sleeps the thread the command runs on. Generated commands are async, so that
+/// is a worker thread rather than the main thread, but only use it for commands that are safe
+/// to call again.
+#[derive(Default)]
+pub struct Retry {
+    policies: HashMap<String, RetryPolicy>,
+}
+
+impl Retry {
+    pub fn new(policies: impl IntoIterator<Item = (&'static str, RetryPolicy)>) -> Self {
+        Retry {
+            policies: policies
+                .into_iter()
+                .map(|(command, policy)| (command.to_string(), policy))
+                .collect(),
+        }
+    }
+
+    /// Sets the policy of a command, replacing one from a decorator.
+    pub fn policy(mut self, command: impl Into<String>, policy: RetryPolicy) -> Self {
+        self.policies.insert(command.into(), policy);
+        self
+    }
+}
+
+impl Middleware for Retry {
+    fn around(&self, ctx: &mut CallContext, next: Next<'_>) -> Result<(), CommandError> {
+        let Some(policy) = self.policies.get(&ctx.command) else {
+            return next.run(ctx);
+        };
+
+        let started = Instant::now();
+        let mut errors = Vec::new();
+        let mut attempt = 1;
+        loop {
+            let err = match next.run(ctx) {
+                Ok(()) => return Ok(()),
+                Err(err) => err,
+            };
+
+            let retryable = policy.retries(&err);
+            errors.push(err.exception.clone().unwrap_or_else(|| err.code.clone()));
+
+            let backoff = policy.backoff(attempt);
+            let past_deadline = ctx
+                .deadline
+                .is_some_and(|deadline| SystemTime::now() + backoff >= deadline);
+
+            if !retryable || attempt >= policy.max_attempts || past_deadline {
+                let mut err = err;
+                err.details.insert(
+                    "retry".to_string(),
+                    json!({
+                        "attempts": attempt,
+                        "max_attempts": policy.max_attempts,
+                        "exhausted": retryable && attempt >= policy.max_attempts,
+                        "past_deadline": past_deadline,
+                        "elapsed_ms": started.elapsed().as_millis() as u64,
+                        "errors": errors,
+                    }),
+                );
+                return Err(err);
+            }
+
+            thread::sleep(backoff);
+            attempt += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dispatch::Dispatcher;
+    use pyo3::exceptions::{PyConnectionError, PyValueError};
+    use pyo3::PyErr;
+    use std::cell::Cell;
+
+    fn policy(max_attempts: u32, multiplier: f64) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts,
+            initial_backoff: Duration::from_millis(100),
+            multiplier,
+            max_backoff: Duration::from_secs(1),
+            retry_on: Vec::new(),
+        }
+    }
+
+    fn call(
+        dispatcher: &Dispatcher,
+        calls: &Cell<u32>,
+        err: fn() -> PyErr,
+        succeed_on: u32,
+    ) -> Result<u32, CommandError> {
+        dispatcher.call("fetch", "main", |_, _| {
+            calls.set(calls.get() + 1);
+            if calls.get() >= succeed_on {
+                Ok(calls.get())
+            } else {
+                Err(err())
+            }
+        })
+    }
+
+    fn connection_error() -> PyErr {
+        PyConnectionError::new_err("connection refused")
+    }
+
+    #[test]
+    fn backoff_grows_up_to_the_maximum() {
+        let policy = policy(10, 2.0);
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(400));
+        assert_eq!(policy.backoff(5), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn backoff_does_not_overflow() {
+        assert_eq!(policy(100, 2.0).backoff(70), Duration::from_secs(1));
+        assert_eq!(policy(100, 2.0).backoff(u32::MAX), Duration::from_secs(1));
+        assert_eq!(policy(100, f64::MAX).backoff(3), Duration::from_secs(1));
+        assert_eq!(policy(100, -2.0).backoff(2), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn retries_until_the_call_succeeds() {
+        let dispatcher = Dispatcher::new().middleware(Retry::default().policy(
+            "fetch",
+            RetryPolicy {
+                initial_backoff: Duration::from_millis(1),
+                ..policy(5, 1.0)
+            },
+        ));
+        let calls = Cell::new(0);
+
+        assert_eq!(call(&dispatcher, &calls, connection_error, 3).unwrap(), 3);
+    }
+
+    #[test]
+    fn reports_attempts_once_exhausted() {
+        let dispatcher = Dispatcher::new().middleware(Retry::default().policy(
+            "fetch",
+            RetryPolicy {
+                initial_backoff: Duration::from_millis(1),
+                ..policy(3, 1.0)
+            },
+        ));
+        let calls = Cell::new(0);
+
+        let err = call(&dispatcher, &calls, connection_error, u32::MAX).unwrap_err();
+        assert_eq!(calls.get(), 3);
+        assert_eq!(err.details["retry"]["attempts"], 3);
+        assert_eq!(err.details["retry"]["exhausted"], true);
+        assert_eq!(
+            err.details["retry"]["errors"],
+            json!(["ConnectionError", "ConnectionError", "ConnectionError"])
+        );
+    }
+
+    #[test]
+    fn only_retries_matching_exceptions() {
+        let dispatcher = Dispatcher::new().middleware(Retry::default().policy(
+            "fetch",
+            RetryPolicy {
+                initial_backoff: Duration::from_millis(1),
+                retry_on: vec!["OSError".to_string()],
+                ..policy(3, 1.0)
+            },
+        ));
+
+        // ConnectionError is a subclass of OSError
+        let calls = Cell::new(0);
+        assert!(call(&dispatcher, &calls, connection_error, 2).is_ok());
+
+        let calls = Cell::new(0);
+        let err = call(
+            &dispatcher,
+            &calls,
+            || PyValueError::new_err("bad input"),
+            u32::MAX,
+        )
+        .unwrap_err();
+        assert_eq!(calls.get(), 1);
+        assert_eq!(err.details["retry"]["exhausted"], false);
+    }
+
+    #[test]
+    fn stops_before_the_deadline() {
+        let dispatcher = Dispatcher::new()
+            .timeout(Duration::from_millis(150))
+            .middleware(Retry::default().policy("fetch", policy(5, 1.0)));
+        let calls = Cell::new(0);
+
+        // The second backoff would end past the deadline
+        let started = Instant::now();
+        let err = call(&dispatcher, &calls, connection_error, u32::MAX).unwrap_err();
+        assert_eq!(calls.get(), 2);
+        assert_eq!(err.details["retry"]["past_deadline"], true);
+        assert!(started.elapsed() < Duration::from_millis(150));
+    }
+}
@@ -0,0 +1,25 @@
+import { fromBinary, type DescMessage, type MessageShape } from "@bufbuild/protobuf";
+
+/** Payload of events emitted with `tauri_py.emit`. */
+export interface BinaryEvent {
+  /** Full name of the protobuf message type, e.g. `TaskProgress`. */
+  messageType: string;
+  /** Base64 encoded protobuf message. */
+  data: string;
+  invocationId?: string;
+}
+
+/**
+ * Decodes the payload of an event emitted with `tauri_py.emit`, given the schema of the
+ * message it carries from `./gen/events_pb`.
+ */
+export function decodeEvent<Desc extends DescMessage>(
+  schema: Desc,
+  payload: BinaryEvent,
+): MessageShape<Desc> {
+  if (payload.messageType !== schema.typeName) {
+    throw new Error(`Expected a ${schema.typeName} event, got ${payload.messageType}`);
+  }
+  const bytes = Uint8Array.from(atob(payload.data), (c) => c.charCodeAt(0));
+  return fromBinary(schema, bytes);
+}